@@ -19,9 +19,11 @@ use {
     openssl::pkey::PKey,
     openssl::nid::Nid,
     openssl::sign::Verifier,
+    once_cell::sync::Lazy,
 };
 
 pub const COMPRESSED_PUBKEY_SERIALIZED_SIZE: usize = 33;
+pub const UNCOMPRESSED_PUBKEY_SERIALIZED_SIZE: usize = 65;
 pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
 pub const SIGNATURE_OFFSETS_SERIALIZED_SIZE: usize = 14;
 // bytemuck requires structures to be aligned
@@ -40,6 +42,33 @@ pub struct Secp256r1SignatureOffsets {
     message_instruction_index: u16, // index of instruction data to get message data
 }
 
+impl Secp256r1SignatureOffsets {
+    /// Low-level constructor for callers that need to reference a signature,
+    /// public key, or message living in a *different* instruction of the
+    /// same transaction. Pass `u16::MAX` for an `*_instruction_index` to mean
+    /// "this instruction", the same convention `verify` already honors.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        signature_offset: u16,
+        signature_instruction_index: u16,
+        public_key_offset: u16,
+        public_key_instruction_index: u16,
+        message_data_offset: u16,
+        message_data_size: u16,
+        message_instruction_index: u16,
+    ) -> Self {
+        Self {
+            signature_offset,
+            signature_instruction_index,
+            public_key_offset,
+            public_key_instruction_index,
+            message_data_offset,
+            message_data_size,
+            message_instruction_index,
+        }
+    }
+}
+
 pub fn new_secp256r1_instruction(signer: &SigningKey, message: &[u8]) -> Instruction {
     let signature = signer.sign(message);
     let signature = signature.normalize_s().unwrap_or(signature).to_vec();
@@ -94,6 +123,145 @@ pub fn new_secp256r1_instruction(signer: &SigningKey, message: &[u8]) -> Instruc
     }
 }
 
+/// Packs `signers_and_messages.len()` signatures into a single secp256r1
+/// instruction, one [`Secp256r1SignatureOffsets`] per signature. Identical
+/// messages are deduplicated so several signatures over the same payload
+/// (e.g. co-signers) share one copy of the message bytes. Useful for
+/// batching many WebAuthn/passkey signature checks into one precompile call.
+pub fn new_secp256r1_instruction_multi(signers_and_messages: &[(&SigningKey, &[u8])]) -> Instruction {
+    let num_signatures = signers_and_messages.len();
+    assert!(num_signatures > 0);
+    assert!(num_signatures <= u8::MAX as usize);
+
+    let signatures: Vec<Vec<u8>> = signers_and_messages
+        .iter()
+        .map(|(signer, message)| {
+            let signature = signer.sign(message);
+            let signature = signature.normalize_s().unwrap_or(signature).to_vec();
+            assert_eq!(signature.len(), SIGNATURE_SERIALIZED_SIZE);
+            signature
+        })
+        .collect();
+    let pubkeys: Vec<_> = signers_and_messages
+        .iter()
+        .map(|(signer, _)| VerifyingKey::from(*signer).to_encoded_point(true).to_bytes())
+        .collect();
+
+    // Dedup identical messages so repeated signatures over the same payload
+    // can share one copy of the message bytes.
+    let mut unique_messages: Vec<&[u8]> = Vec::new();
+    let message_indices: Vec<usize> = signers_and_messages
+        .iter()
+        .map(|(_, message)| {
+            if let Some(index) = unique_messages.iter().position(|m| m == message) {
+                index
+            } else {
+                unique_messages.push(*message);
+                unique_messages.len() - 1
+            }
+        })
+        .collect();
+
+    let header_size =
+        SIGNATURE_OFFSETS_START.saturating_add(SIGNATURE_OFFSETS_SERIALIZED_SIZE.saturating_mul(num_signatures));
+
+    let mut offset = header_size;
+    let pubkey_offsets: Vec<usize> = pubkeys
+        .iter()
+        .map(|pubkey| {
+            let this_offset = offset;
+            offset = offset.saturating_add(pubkey.len());
+            this_offset
+        })
+        .collect();
+    let signature_offsets: Vec<usize> = signatures
+        .iter()
+        .map(|signature| {
+            let this_offset = offset;
+            offset = offset.saturating_add(signature.len());
+            this_offset
+        })
+        .collect();
+    let message_offsets: Vec<usize> = unique_messages
+        .iter()
+        .map(|message| {
+            let this_offset = offset;
+            offset = offset.saturating_add(message.len());
+            this_offset
+        })
+        .collect();
+
+    let mut instruction_data = Vec::with_capacity(offset);
+    instruction_data.extend_from_slice(bytes_of(&[num_signatures as u8, 0]));
+
+    for i in 0..num_signatures {
+        let message_index = message_indices[i];
+        let message = unique_messages[message_index];
+        let offsets = Secp256r1SignatureOffsets::new(
+            signature_offsets[i] as u16,
+            u16::MAX,
+            pubkey_offsets[i] as u16,
+            u16::MAX,
+            message_offsets[message_index] as u16,
+            message.len() as u16,
+            u16::MAX,
+        );
+        instruction_data.extend_from_slice(bytes_of(&offsets));
+    }
+
+    for pubkey in &pubkeys {
+        instruction_data.extend_from_slice(pubkey);
+    }
+    for signature in &signatures {
+        instruction_data.extend_from_slice(signature);
+    }
+    for message in &unique_messages {
+        instruction_data.extend_from_slice(message);
+    }
+
+    debug_assert_eq!(instruction_data.len(), offset);
+
+    Instruction {
+        program_id: solana_sdk::secp256r1_program::id(),
+        accounts: vec![],
+        data: instruction_data,
+    }
+}
+
+/// The secp256r1 curve group and the constants derived from its order never
+/// change, so they're computed once and reused across every `verify` call
+/// instead of being recreated per signature.
+///
+/// Sharing one `EcGroup`/`BigNum` across concurrent `verify` calls is sound:
+/// `openssl` marks both types `Send + Sync` (they wrap reference-counted,
+/// otherwise-immutable OpenSSL objects), and nothing here ever mutates them
+/// after construction -- every call site below only takes `group`/`order`/
+/// `half_order`/`n_minus_one` by shared reference. All mutable scratch state
+/// (`BigNumContext`, and every `BigNum`/`EcPoint` produced during signature
+/// math) is freshly allocated per `verify` call, never shared.
+static SECP256R1_GROUP_PARAMS: Lazy<(EcGroup, BigNum, BigNum, BigNum)> = Lazy::new(|| {
+    let group =
+        EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).expect("secp256r1 curve group is always available");
+    let mut ctx = BigNumContext::new().expect("bignum context");
+    let mut order = BigNum::new().expect("bignum");
+    group
+        .order(&mut order, &mut ctx)
+        .expect("secp256r1 curve order");
+
+    // Calculate half_order = order / 2
+    let mut half_order = BigNum::new().expect("bignum");
+    half_order.rshift1(&order).expect("half order");
+
+    // Calculate n_minus_one = order - 1
+    let one = BigNum::from_u32(1).expect("bignum");
+    let mut n_minus_one = BigNum::new().expect("bignum");
+    n_minus_one
+        .checked_sub(&order, &one)
+        .expect("order minus one");
+
+    (group, order, half_order, n_minus_one)
+});
+
 pub fn verify(
     data: &[u8],
     instruction_datas: &[&[u8]],
@@ -113,6 +281,17 @@ pub fn verify(
     if data.len() < expected_data_size {
         return Err(PrecompileError::InvalidInstructionDataSize);
     }
+
+    // The curve group and its order never change between calls, so they're
+    // memoized behind a `Lazy` rather than recomputed on every `verify` call.
+    // `half_order` and `n_minus_one` are cheap derivatives of `order` and are
+    // memoized alongside it. `BigNumContext` holds scratch state for the
+    // OpenSSL bignum math and isn't `Sync`, so it's still created fresh here,
+    // but only once per `verify` call rather than once per signature.
+    let (group, _order, half_order, n_minus_one) = &*SECP256R1_GROUP_PARAMS;
+    let mut ctx = BigNumContext::new().map_err(|_| PrecompileError::InvalidSignature)?;
+    let one = BigNum::from_u32(1).map_err(|_| PrecompileError::InvalidSignature)?;
+
     for i in 0..num_signatures {
         let start = i
             .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
@@ -132,13 +311,23 @@ pub fn verify(
             SIGNATURE_SERIALIZED_SIZE,
         )?;
 
-        // Parse out pubkey
+        // Parse out pubkey. The leading tag byte tells us whether it's
+        // compressed (0x02/0x03, 33 bytes) or uncompressed/hybrid (0x04/0x06/
+        // 0x07, 65 bytes).
+        let tag = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.public_key_instruction_index,
+            offsets.public_key_offset,
+            1,
+        )?[0];
+        let pubkey_size = pubkey_serialized_size(tag)?;
         let pubkey = get_data_slice(
             data,
             instruction_datas,
             offsets.public_key_instruction_index,
             offsets.public_key_offset,
-            COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+            pubkey_size,
         )?;
 
         // Parse out message
@@ -150,30 +339,17 @@ pub fn verify(
             offsets.message_data_size as usize,
         )?;
 
-        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(|_| PrecompileError::InvalidSignature)?;
-        let mut ctx = BigNumContext::new().map_err(|_| PrecompileError::InvalidSignature)?;
-        let mut order = BigNum::new().map_err(|_| PrecompileError::InvalidSignature)?;
-
-        group.order(&mut order, &mut ctx).map_err(|_| PrecompileError::InvalidSignature)?;
-
-        // Calculate half_order = order / 2
-        let mut half_order = BigNum::new().map_err(|_| PrecompileError::InvalidSignature)?;
-        half_order.rshift1(&order).map_err(|_| PrecompileError::InvalidSignature)?;
-
-        // Calculate n_minus_one = order - 1
-        let one = BigNum::from_u32(1).map_err(|_| PrecompileError::InvalidSignature)?;
-        let mut n_minus_one = BigNum::new().map_err(|_| PrecompileError::InvalidSignature)?;
-        n_minus_one.checked_sub(&order, &one).map_err(|_| PrecompileError::InvalidSignature)?;
-
         let r_bignum = BigNum::from_slice(&signature[..32]).map_err(|_| PrecompileError::InvalidSignature)?;
         let s_bignum = BigNum::from_slice(&signature[32..]).map_err(|_| PrecompileError::InvalidSignature)?;
     
         // Since OpenSSL doesnt know what curve this signature is for, we have
         // to check that r and s are within the order of the curve.
-        let within_order_minus_one = r_bignum > one && r_bignum < n_minus_one && s_bignum > one && s_bignum < n_minus_one;
+        let within_order_minus_one =
+            r_bignum > one && r_bignum < *n_minus_one && s_bignum > one && s_bignum < *n_minus_one;
         if !within_order_minus_one {
             return Err(PrecompileError::InvalidSignature);
         }
+
         // Create an ECDSA signature object from the ASN.1 integers
         let ecdsa_sig = openssl::ecdsa::EcdsaSig::from_private_components(r_bignum, s_bignum).map_err(|_| PrecompileError::InvalidSignature)?;
         //println!("Sig: {:?}", ecdsa_sig.to_der().map_err(|_| PrecompileError::InvalidSignature)?);
@@ -181,12 +357,13 @@ pub fn verify(
     
 
         // Enforce Low-S
-        if ecdsa_sig.s() > &half_order {
+        if ecdsa_sig.s() > half_order {
             return Err(PrecompileError::InvalidSignature);
         }
 
-        let public_key_point = EcPoint::from_bytes(&group, pubkey, &mut ctx).map_err(|_| PrecompileError::InvalidPublicKey)?;
-        let public_key = EcKey::from_public_key(&group, &public_key_point).map_err(|_| PrecompileError::InvalidPublicKey)?;
+        let public_key_point =
+            EcPoint::from_bytes(group, pubkey, &mut ctx).map_err(|_| PrecompileError::InvalidPublicKey)?;
+        let public_key = EcKey::from_public_key(group, &public_key_point).map_err(|_| PrecompileError::InvalidPublicKey)?;
         let pkey = PKey::from_ec_key(public_key).map_err(|_| PrecompileError::InvalidPublicKey)?;
 
         let mut verifier = Verifier::new(openssl::hash::MessageDigest::sha256(), &pkey).map_err(|_| PrecompileError::InvalidSignature)?;
@@ -200,6 +377,17 @@ pub fn verify(
     Ok(())
 }
 
+/// Maps a SEC1 public-key tag byte to the expected serialized size: 0x02/0x03
+/// for a 33-byte compressed point, 0x04/0x06/0x07 for a 65-byte
+/// uncompressed/hybrid point.
+fn pubkey_serialized_size(tag: u8) -> Result<usize, PrecompileError> {
+    match tag {
+        0x02 | 0x03 => Ok(COMPRESSED_PUBKEY_SERIALIZED_SIZE),
+        0x04 | 0x06 | 0x07 => Ok(UNCOMPRESSED_PUBKEY_SERIALIZED_SIZE),
+        _ => Err(PrecompileError::InvalidPublicKey),
+    }
+}
+
 fn get_data_slice<'a>(
     data: &'a [u8],
     instruction_datas: &'a [&[u8]],
@@ -252,9 +440,15 @@ pub mod test {
         let mut instruction_data = vec![0u8; DATA_START];
         instruction_data[0..SIGNATURE_OFFSETS_START].copy_from_slice(bytes_of(&num_signatures));
         instruction_data[SIGNATURE_OFFSETS_START..DATA_START].copy_from_slice(bytes_of(offsets));
+
+        // A valid compressed-point tag byte at the default public key offset
+        // (0) so tests exercising other offset fields don't spuriously trip
+        // the tag-byte validation added for uncompressed/hybrid key support.
+        let mut other_instruction = [0u8; 100];
+        other_instruction[0] = 0x02;
         verify(
             &instruction_data,
-            &[&[0u8; 100]],
+            &[&other_instruction],
             &FeatureSet::all_enabled(),
         )
     }
@@ -360,12 +554,25 @@ pub mod test {
             Err(PrecompileError::InvalidDataOffsets)
         );
 
+        // A valid compressed-point tag byte sits just inside the buffer, but
+        // the 33-byte key it implies runs past the end: still an offsets
+        // error, not a pubkey-decoding error.
+        let public_key_offset = 100 - COMPRESSED_PUBKEY_SERIALIZED_SIZE as u16 + 1;
         let offsets = Secp256r1SignatureOffsets {
-            public_key_offset: 100 - COMPRESSED_PUBKEY_SERIALIZED_SIZE as u16 + 1,
+            public_key_offset,
             ..Secp256r1SignatureOffsets::default()
         };
+        let mut instruction_data = vec![0u8; DATA_START];
+        instruction_data[0..SIGNATURE_OFFSETS_START].copy_from_slice(bytes_of(&1u16));
+        instruction_data[SIGNATURE_OFFSETS_START..DATA_START].copy_from_slice(bytes_of(&offsets));
+        let mut other_instruction = [0u8; 100];
+        other_instruction[public_key_offset as usize] = 0x02;
         assert_eq!(
-            test_case(1, &offsets),
+            verify(
+                &instruction_data,
+                &[&other_instruction],
+                &FeatureSet::all_enabled(),
+            ),
             Err(PrecompileError::InvalidDataOffsets)
         );
     }
@@ -426,4 +633,75 @@ pub mod test {
         );
         assert!(tx.verify_precompiles(&feature_set).is_err());
     }
+
+    #[test]
+    fn test_secp256r1_uncompressed_pubkey() {
+        let privkey = p256::ecdsa::SigningKey::random(rand::thread_rng());
+        let message: &[u8] = b"uncompressed pubkey";
+        let signature = p256::ecdsa::signature::Signer::sign(&privkey, message);
+        let signature = signature.normalize_s().unwrap_or(signature).to_vec();
+        let pubkey = VerifyingKey::from(&privkey).to_encoded_point(false).to_bytes();
+        assert_eq!(pubkey.len(), UNCOMPRESSED_PUBKEY_SERIALIZED_SIZE);
+
+        let public_key_offset = DATA_START;
+        let signature_offset = public_key_offset + UNCOMPRESSED_PUBKEY_SERIALIZED_SIZE;
+        let message_data_offset = signature_offset + SIGNATURE_SERIALIZED_SIZE;
+
+        let mut instruction_data = vec![0u8; DATA_START];
+        instruction_data[0] = 1;
+        let offsets = Secp256r1SignatureOffsets {
+            signature_offset: signature_offset as u16,
+            signature_instruction_index: u16::MAX,
+            public_key_offset: public_key_offset as u16,
+            public_key_instruction_index: u16::MAX,
+            message_data_offset: message_data_offset as u16,
+            message_data_size: message.len() as u16,
+            message_instruction_index: u16::MAX,
+        };
+        instruction_data[SIGNATURE_OFFSETS_START..DATA_START].copy_from_slice(bytes_of(&offsets));
+        instruction_data.extend_from_slice(&pubkey);
+        instruction_data.extend_from_slice(&signature);
+        instruction_data.extend_from_slice(message);
+
+        assert_eq!(
+            verify(&instruction_data, &[&[0u8; 0]], &FeatureSet::all_enabled()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_secp256r1_multi() {
+        solana_logger::setup();
+        let privkey1 = p256::ecdsa::SigningKey::random(rand::thread_rng());
+        let privkey2 = p256::ecdsa::SigningKey::random(rand::thread_rng());
+        let shared_message: &[u8] = b"shared message";
+
+        let instruction = new_secp256r1_instruction_multi(&[
+            (&privkey1, b"hello"),
+            (&privkey2, shared_message),
+            // Same message, different signer: should dedup the message bytes.
+            (&privkey1, shared_message),
+        ]);
+
+        let mint_keypair = Keypair::new();
+        let feature_set = FeatureSet::all_enabled();
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction.clone()],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            Hash::default(),
+        );
+        assert!(tx.verify_precompiles(&feature_set).is_ok());
+
+        let mut bad_instruction = instruction;
+        let last_byte = bad_instruction.data.len() - 1;
+        bad_instruction.data[last_byte] = bad_instruction.data[last_byte].wrapping_add(1);
+        let tx = Transaction::new_signed_with_payer(
+            &[bad_instruction],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            Hash::default(),
+        );
+        assert!(tx.verify_precompiles(&feature_set).is_err());
+    }
 }
\ No newline at end of file